@@ -1,6 +1,15 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "std")]
+extern crate core;
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+
 mod state;
 use state::State;
 
+// `main` is std-only; `state` compiles under `#![no_std]` when `std` is off.
+#[cfg(feature = "std")]
 fn main() {
     let state = State::new(&[
         0x00,
@@ -36,9 +45,12 @@ fn main() {
         0xD0,
         0xFF,
     ]);
-    let final_state = state.start();
-
-    println!("--- FINAL MEMORY ---");
-    final_state.print_memory(30);
+    match state.start() {
+        Ok(final_state) => {
+            println!("--- FINAL MEMORY ---");
+            final_state.print_memory(30);
+        }
+        Err(trap) => println!("Execution halted with a trap: {:?}", trap),
+    }
 }
 
@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+use state::operator;
+
+#[derive(Debug, PartialEq)]
+pub enum AssembleError {
+    UnknownMnemonic(String),
+    UnknownLabel(String),
+    MissingArgument(String),
+    InvalidArgument(String),
+    AddressOutOfRange(usize),
+}
+
+struct Instruction {
+    address: usize,
+    mnemonic: String,
+    operand: Option<String>,
+}
+
+/// Assembles one instruction per line (`LDA 10`, `JMP loop`, `HLT`) into the
+/// raw bytes `State::new` expects. Labels (`loop:`) are resolved in a second
+/// pass once every instruction's address is known.
+pub fn assemble(src: &str) -> Result<Vec<u8>, AssembleError> {
+    let mut labels: HashMap<String, usize> = HashMap::new();
+    let mut instructions: Vec<Instruction> = Vec::new();
+    let mut address = 0usize;
+
+    for raw_line in src.lines() {
+        let mut line = strip_comment(raw_line).trim();
+
+        while let Some(colon) = line.find(':') {
+            let label = line[..colon].trim().to_string();
+            labels.insert(label, address);
+            line = line[colon + 1..].trim();
+        }
+
+        if line.is_empty() {
+            continue;
+        }
+
+        let mut parts = line.splitn(2, char::is_whitespace);
+        let mnemonic = parts.next().unwrap_or("").to_string();
+        let operand = parts
+            .next()
+            .map(|operand| operand.trim().to_string())
+            .filter(|operand| !operand.is_empty());
+
+        if mnemonic.eq_ignore_ascii_case("ORG") {
+            let operand = operand.ok_or_else(|| AssembleError::MissingArgument(mnemonic.clone()))?;
+            address = parse_number(&operand)
+                .ok_or_else(|| AssembleError::InvalidArgument(operand.clone()))? as usize;
+            continue;
+        }
+
+        let size = instruction_size(&mnemonic)?;
+        instructions.push(Instruction {
+            address,
+            mnemonic,
+            operand,
+        });
+        address += size;
+    }
+
+    // Addresses are sized against the fixed 256-byte Neander memory up
+    // front, rather than against wherever the `ORG`-driven cursor happened
+    // to land last: `ORG` can move the cursor backward (e.g. to lay out
+    // data ahead of code), so the final cursor position is not the high
+    // water mark of every address actually written.
+    let mut memory = [0x00; 256];
+
+    for instruction in &instructions {
+        if instruction.mnemonic.eq_ignore_ascii_case("DB") {
+            let operand = instruction
+                .operand
+                .as_ref()
+                .ok_or_else(|| AssembleError::MissingArgument(instruction.mnemonic.clone()))?;
+            let value = parse_number(operand)
+                .ok_or_else(|| AssembleError::InvalidArgument(operand.clone()))?;
+            write_byte(&mut memory, instruction.address, value)?;
+            continue;
+        }
+
+        let operator = operator::mnemonic_operator(&instruction.mnemonic)
+            .ok_or_else(|| AssembleError::UnknownMnemonic(instruction.mnemonic.clone()))?;
+        write_byte(&mut memory, instruction.address, operator::base_opcode(operator.mnemonic))?;
+
+        if operator.requires_arg {
+            let operand = instruction
+                .operand
+                .as_ref()
+                .ok_or_else(|| AssembleError::MissingArgument(instruction.mnemonic.clone()))?;
+            let value = parse_number(operand)
+                .or_else(|| labels.get(operand).map(|address| *address as u8))
+                .ok_or_else(|| AssembleError::UnknownLabel(operand.clone()))?;
+            write_byte(&mut memory, instruction.address + 1, value)?;
+        }
+    }
+
+    Ok(memory.to_vec())
+}
+
+/// Writes a byte into the fixed-size memory buffer, rejecting any address
+/// that falls outside the 256 bytes `State` can actually address instead of
+/// panicking on an out-of-range index.
+fn write_byte(memory: &mut [u8; 256], address: usize, value: u8) -> Result<(), AssembleError> {
+    let slot = memory
+        .get_mut(address)
+        .ok_or(AssembleError::AddressOutOfRange(address))?;
+    *slot = value;
+    Ok(())
+}
+
+fn instruction_size(mnemonic: &str) -> Result<usize, AssembleError> {
+    if mnemonic.eq_ignore_ascii_case("DB") {
+        return Ok(1);
+    }
+
+    operator::mnemonic_operator(mnemonic)
+        .map(|operator| if operator.requires_arg { 2 } else { 1 })
+        .ok_or_else(|| AssembleError::UnknownMnemonic(mnemonic.to_string()))
+}
+
+fn strip_comment(line: &str) -> &str {
+    match line.find(';') {
+        Some(index) => &line[..index],
+        None => line,
+    }
+}
+
+fn parse_number(text: &str) -> Option<u8> {
+    let text = text.trim();
+    if text.len() > 1 && (&text[..2] == "0x" || &text[..2] == "0X") {
+        u8::from_str_radix(&text[2..], 16).ok()
+    } else {
+        text.parse::<u8>().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn assemble_resolves_labels_and_db() {
+        let program = "LDI 0x05\nloop:\nSUB one\nJNZ loop\nHLT\none: DB 0x01\n";
+        let memory = assemble(program).unwrap();
+
+        assert_eq!(memory.len(), 256);
+        assert_eq!(
+            &memory[..8],
+            &[0xE0, 0x05, 0x70, 0x07, 0xB0, 0x02, 0xF0, 0x01]
+        );
+    }
+
+    #[test]
+    fn assemble_allows_org_to_move_the_cursor_backward() {
+        let program = "ORG 0x10\nDB 0x00\nORG 0x00\nLDA 0x10\nADD 0x10\nHLT\n";
+        let memory = assemble(program).unwrap();
+
+        assert_eq!(memory.len(), 256);
+        assert_eq!(&memory[..5], &[0x20, 0x10, 0x30, 0x10, 0xF0]);
+        assert_eq!(memory[0x10], 0x00);
+    }
+
+    #[test]
+    fn assemble_rejects_an_instruction_that_overruns_the_256_byte_memory() {
+        let program = "ORG 0xFF\nLDA 0x00\n";
+        assert_eq!(assemble(program), Err(AssembleError::AddressOutOfRange(256)));
+    }
+}
@@ -0,0 +1,127 @@
+#[cfg(feature = "std")]
+pub mod assembler;
+pub mod operator;
+pub mod trap;
+
+use state::operator::get_operator;
+use state::trap::Trap;
+
+#[derive(Copy, Clone)]
+pub struct State {
+    pub pc: usize,
+    pub ac: u8,
+    pub halt: bool,
+    pub n: bool,
+    pub z: bool,
+    pub carry: bool,
+    pub cycles: u64,
+    pub memory: [u8; 256],
+    pub inputs: [u8; 256],
+    pub output: [u8; 40],
+}
+
+impl State {
+    pub fn new(program: &[u8]) -> State {
+        let mut memory = [0x00; 256];
+        for (address, byte) in program.iter().enumerate() {
+            memory[address] = *byte;
+        }
+
+        State {
+            pc: 0,
+            ac: 0,
+            halt: false,
+            n: false,
+            z: true,
+            carry: false,
+            cycles: 0,
+            memory,
+            inputs: [0x00; 256],
+            output: [0x00; 40],
+        }
+    }
+
+    pub fn start(&self) -> Result<State, Trap> {
+        self.run(None)
+    }
+
+    /// Like `start`, but returns `Err(Trap::StepLimitExceeded)` once
+    /// `max_steps` instructions have executed, so a runaway program (e.g. a
+    /// bad `JMP`) can't loop forever.
+    pub fn run_with_limit(&self, max_steps: u64) -> Result<State, Trap> {
+        self.run(Some(max_steps))
+    }
+
+    fn run(&self, max_steps: Option<u64>) -> Result<State, Trap> {
+        let mut state = *self;
+
+        while !state.halt {
+            if let Some(max_steps) = max_steps {
+                if state.cycles >= max_steps {
+                    return Err(Trap::StepLimitExceeded);
+                }
+            }
+
+            let opcode = *state
+                .memory
+                .get(state.pc)
+                .ok_or(Trap::OutOfBounds(state.pc))?;
+            let operator = get_operator(&opcode)?;
+            let argument = if operator.requires_arg {
+                *state
+                    .memory
+                    .get(state.pc + 1)
+                    .ok_or(Trap::OutOfBounds(state.pc + 1))?
+            } else {
+                0x00
+            };
+
+            state = (operator.run)(&state, argument)?;
+            state.cycles += 1;
+        }
+
+        Ok(state)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn print_memory(&self, count: usize) {
+        println!(
+            "AC: {:#04X}  N: {}  Z: {}  Carry: {}  Cycles: {}",
+            self.ac, self.n, self.z, self.carry, self.cycles
+        );
+        for (address, value) in self.memory[..count].iter().enumerate() {
+            println!("{:#04X}: {:#04X}", address, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn run_with_limit_stops_a_runaway_jmp() {
+        let state = State::new(&[0x80, 0x00]);
+        match state.run_with_limit(5) {
+            Err(Trap::StepLimitExceeded) => {}
+            other => panic!("expected StepLimitExceeded, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn run_with_limit_returns_the_final_state_within_budget() {
+        let state = State::new(&[0xF0]);
+        let final_state = state.run_with_limit(5).unwrap();
+        assert!(final_state.halt);
+        assert_eq!(final_state.cycles, 1);
+    }
+
+    #[test]
+    fn start_traps_out_of_bounds_instead_of_panicking_on_a_runaway_pc() {
+        let state = State::new(&[0x00; 256]);
+        match state.start() {
+            Err(Trap::OutOfBounds(256)) => {}
+            other => panic!("expected OutOfBounds(256), got {:?}", other.map(|_| ())),
+        }
+    }
+}
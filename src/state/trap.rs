@@ -0,0 +1,8 @@
+/// An error returned by an `Operator` or the fetch/decode loop instead of
+/// panicking.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Trap {
+    InvalidOpcode(u8),
+    OutOfBounds(usize),
+    StepLimitExceeded,
+}